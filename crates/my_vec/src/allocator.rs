@@ -0,0 +1,173 @@
+use std::{
+    alloc::{alloc, dealloc, realloc, Layout},
+    ptr::{self, NonNull},
+};
+
+/// Returned when an [Allocator] cannot satisfy a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+impl core::fmt::Display for AllocError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "memory allocation failed")
+    }
+}
+impl core::error::Error for AllocError {}
+
+/// A source of memory for [RawDynamicSizeArray](crate::RawDynamicSizeArray).
+///
+/// Modeled on the stable-channel `allocator-api2` design so a
+/// [DynamicSizeArray](crate::DynamicSizeArray) can be backed by an arena, a bump
+/// allocator or a fixed-region pool instead of always hitting the global heap.
+///
+/// # Safety
+/// Implementations must hand out blocks that stay valid until `deallocate` is
+/// called and must treat the `Layout` passed to `deallocate`/`grow` as the one
+/// the block was allocated with.
+pub unsafe trait Allocator {
+    /// Allocate a block fitting `layout`, returning a slice covering its
+    /// (possibly larger) usable size.
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError>;
+
+    /// Deallocate the block at `pointer`.
+    ///
+    /// # Safety
+    /// `pointer` must denote a block currently allocated by this allocator with
+    /// `layout`.
+    unsafe fn deallocate(&self, pointer: NonNull<u8>, layout: Layout);
+
+    /// Grow the block at `pointer` from `old_layout` to `new_layout`.
+    ///
+    /// The default implementation allocates a fresh block, copies the old bytes
+    /// across and deallocates the old block.
+    ///
+    /// # Safety
+    /// `pointer` must denote a block currently allocated by this allocator with
+    /// `old_layout`, and `new_layout.size()` must be at least `old_layout.size()`.
+    unsafe fn grow(
+        &self,
+        pointer: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let new_pointer = self.allocate(new_layout)?;
+
+        // SAFETY:
+        // `pointer` is valid for reads of `old_layout.size()` bytes by contract,
+        // `new_pointer` is valid for writes of at least that many because
+        // `new_layout.size() >= old_layout.size()`, and the two blocks do not overlap.
+        unsafe {
+            ptr::copy_nonoverlapping(
+                pointer.as_ptr(),
+                new_pointer.as_ptr() as *mut u8,
+                old_layout.size(),
+            );
+            self.deallocate(pointer, old_layout);
+        }
+
+        Ok(new_pointer)
+    }
+
+    /// Shrink the block at `pointer` from `old_layout` to the smaller `new_layout`.
+    ///
+    /// The default implementation allocates a smaller block, copies the
+    /// still-live bytes across and deallocates the old block.
+    ///
+    /// # Safety
+    /// `pointer` must denote a block currently allocated by this allocator with
+    /// `old_layout`, and `new_layout.size()` must be at most `old_layout.size()`.
+    unsafe fn shrink(
+        &self,
+        pointer: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let new_pointer = self.allocate(new_layout)?;
+
+        // SAFETY:
+        // `pointer` is valid for reads of at least `new_layout.size()` bytes
+        // because `new_layout.size() <= old_layout.size()`, `new_pointer` is
+        // valid for that many writes, and the blocks do not overlap.
+        unsafe {
+            ptr::copy_nonoverlapping(
+                pointer.as_ptr(),
+                new_pointer.as_ptr() as *mut u8,
+                new_layout.size(),
+            );
+            self.deallocate(pointer, old_layout);
+        }
+
+        Ok(new_pointer)
+    }
+}
+
+/// The global allocator, forwarding every request to [std::alloc].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Global;
+
+// SAFETY:
+// Every block comes from the global allocator and is freed by it with the same
+// layout, so the invariants of [Allocator] hold.
+unsafe impl Allocator for Global {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let pointer = if layout.size() == 0 {
+            // A zero-sized allocation never touches the heap; hand back an
+            // aligned dangling pointer.
+            NonNull::new(layout.align() as *mut u8).ok_or(AllocError)?
+        } else {
+            // SAFETY: `layout.size()` is non-zero.
+            NonNull::new(unsafe { alloc(layout) }).ok_or(AllocError)?
+        };
+
+        Ok(NonNull::slice_from_raw_parts(pointer, layout.size()))
+    }
+
+    unsafe fn deallocate(&self, pointer: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            // SAFETY: `pointer` came from `alloc` with `layout`, see `allocate`.
+            unsafe { dealloc(pointer.as_ptr(), layout) }
+        }
+    }
+
+    unsafe fn grow(
+        &self,
+        pointer: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        // Reuse `realloc` whenever we actually held a block, falling back to a
+        // fresh allocation when the old block was zero-sized.
+        if old_layout.size() == 0 {
+            return self.allocate(new_layout);
+        }
+
+        // SAFETY:
+        // `pointer`/`old_layout` describe a live global allocation and
+        // `new_layout.size()` is non-zero and within `isize::MAX` by contract.
+        let new_pointer =
+            NonNull::new(unsafe { realloc(pointer.as_ptr(), old_layout, new_layout.size()) })
+                .ok_or(AllocError)?;
+
+        Ok(NonNull::slice_from_raw_parts(new_pointer, new_layout.size()))
+    }
+
+    unsafe fn shrink(
+        &self,
+        pointer: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        debug_assert!(
+            new_layout.size() != 0,
+            "zero-sized shrink should be handled by the caller via deallocate"
+        );
+
+        // SAFETY:
+        // `pointer`/`old_layout` describe a live global allocation and
+        // `new_layout.size()` is non-zero and no larger than `old_layout.size()`.
+        let new_pointer =
+            NonNull::new(unsafe { realloc(pointer.as_ptr(), old_layout, new_layout.size()) })
+                .ok_or(AllocError)?;
+
+        Ok(NonNull::slice_from_raw_parts(new_pointer, new_layout.size()))
+    }
+}
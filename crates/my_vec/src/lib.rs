@@ -1,120 +1,258 @@
+pub mod allocator;
 pub mod error;
 
 use std::{
-    alloc::{Layout, LayoutError, alloc, dealloc, handle_alloc_error, realloc},
-    mem::size_of,
+    alloc::{Layout, LayoutError, alloc_zeroed},
+    mem::{align_of, size_of},
     ops::{Deref, DerefMut},
     ptr::{self, NonNull},
     usize,
 };
 
+use allocator::{Allocator, Global};
 use error::GrowError;
 
 use crate::error::InsertError;
 
 pub const MAX_ALLOCATION_SIZE: usize = isize::MAX as _;
 
-pub struct RawDynamicSizeArray<T> {
+pub struct RawDynamicSizeArray<T, A: Allocator = Global> {
     elements: NonNull<T>,
     capacity: usize,
+    allocator: A,
 }
 
-unsafe impl<T: Send> Send for RawDynamicSizeArray<T> {}
-unsafe impl<T: Sync> Sync for RawDynamicSizeArray<T> {}
+unsafe impl<T: Send, A: Allocator + Send> Send for RawDynamicSizeArray<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for RawDynamicSizeArray<T, A> {}
 
 // constructors
-impl<T> RawDynamicSizeArray<T> {
+impl<T> RawDynamicSizeArray<T, Global> {
+    pub const fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: Allocator> RawDynamicSizeArray<T, A> {
     const ELEMENT_SIZE: usize = size_of::<T>();
-    const NEW_CAPACITY: usize = if Self::ELEMENT_SIZE == 0 {
-        usize::MAX
-    } else {
-        usize::MIN
-    };
+    /// Whether `T` is a zero-sized type, which we never allocate for.
+    const IS_ZST: bool = Self::ELEMENT_SIZE == 0;
+    const NEW_CAPACITY: usize = if Self::IS_ZST { usize::MAX } else { usize::MIN };
 
-    pub const fn new() -> Self {
+    /// Create an empty buffer that routes every allocation through `allocator`.
+    pub const fn new_in(allocator: A) -> Self {
         Self {
             elements: NonNull::dangling(),
             capacity: Self::NEW_CAPACITY,
+            allocator,
         }
     }
 }
 
 // accessors
-impl<T> RawDynamicSizeArray<T> {
+impl<T, A: Allocator> RawDynamicSizeArray<T, A> {
     const fn layout(&self) -> Result<Layout, LayoutError> {
         Layout::array::<T>(self.capacity)
     }
 }
 
 // mutators
-impl<T> RawDynamicSizeArray<T> {
+impl<T, A: Allocator> RawDynamicSizeArray<T, A> {
     /// Extend capacity by doubling or adding 1 at first call.
     pub fn grow(&mut self) -> Result<(), GrowError> {
-        // [3]
-        let (new_layout, new_pointer) = if self.capacity == 0 {
-            self.capacity = 1;
-            let new_layout = self.layout()?;
-            // SAFETY: new_capacity == 1
-            let new_pointer = unsafe { alloc(new_layout) }; // [1]
-
-            (new_layout, new_pointer)
+        // A ZST has effectively infinite capacity (`usize::MAX`) and is never
+        // backed by a real allocation, so there is nothing to grow.
+        if Self::IS_ZST {
+            return Ok(());
+        }
+
+        // Hand the actual work to the non-generic core so it is compiled once
+        // and shared by every `T`/`A` rather than monomorphized per element type.
+        let mut elements = self.elements.cast::<u8>();
+        grow_raw(
+            &self.allocator,
+            &mut elements,
+            &mut self.capacity,
+            Self::ELEMENT_SIZE,
+            align_of::<T>(),
+        )?;
+        self.elements = elements.cast::<T>();
+
+        Ok(())
+    }
+
+    /// Reallocate the buffer once so it holds exactly `new_cap` elements.
+    ///
+    /// Unlike [Self::grow] this jumps straight to a caller-chosen capacity in a
+    /// single `realloc`, which is what the amortized [DynamicSizeArray::reserve]
+    /// path wants when it knows how many elements are about to be appended.
+    pub fn grow_to(&mut self, new_cap: usize) -> Result<(), GrowError> {
+        // ZSTs keep their `usize::MAX` capacity and are never allocated for.
+        if Self::IS_ZST {
+            return Ok(());
+        }
+
+        debug_assert!(new_cap >= self.capacity, "grow_to must never shrink");
+
+        // [5]
+        let new_layout = Layout::array::<T>(new_cap)
+            .map_err(GrowError::Layout)
+            .and_then(|new_layout| {
+                if new_layout.size() <= MAX_ALLOCATION_SIZE {
+                    Ok(new_layout)
+                } else {
+                    Err(GrowError::AllocationTooLarge)
+                }
+            })?;
+
+        let allocation = if self.capacity == 0 {
+            // SAFETY: nothing was allocated yet, so there is no old block to move.
+            self.allocator.allocate(new_layout)
         } else {
             let old_layout = self.layout()?;
-            let old_pointer = self.elements.as_ptr() as _;
-
-            self.capacity *= 2; // [4]
-            let new_layout = self
-                .layout()
-                .map_err(GrowError::Layout)
-                .and_then(|new_capacity| {
-                    //[5]
-                    if new_capacity.size() <= MAX_ALLOCATION_SIZE {
-                        Ok(new_capacity)
-                    } else {
-                        Err(GrowError::AllocationTooLarge)
-                    }
-                })?;
+            let old_pointer = self.elements.cast::<u8>();
 
             // SAFETY:
-            // `old_pointer` was allocated with [alloc::alloc] using the same global allocator
-            // `old_layout` was use to allocate and is therefore the same as the size used to allocate. see [1], [2]
-            // `new_layout.size()` is unsigned and not 0. see [3], [4]
-            // `new_layout.size()` <= [isize::MAX]. see [5]
-            let new_pointer = unsafe { realloc(old_pointer, old_layout, new_layout.size()) }; // [2]
-
-            (new_layout, new_pointer)
+            // `old_pointer` was allocated by `self.allocator` with `old_layout`.
+            // `new_layout.size()` is non-zero and <= [isize::MAX]. see [5]
+            // `new_cap >= self.capacity` so the block only ever grows.
+            unsafe { self.allocator.grow(old_pointer, old_layout, new_layout) }
         };
 
-        self.elements =
-            NonNull::new(new_pointer as _).ok_or(GrowError::AllocationFail(new_layout))?;
+        self.elements = allocation
+            .map_err(|_| GrowError::AllocationFail(new_layout))?
+            .cast::<T>();
+        self.capacity = new_cap;
 
         Ok(())
     }
+
+    /// Reallocate the buffer down to `new_cap` elements.
+    ///
+    /// Shrinking to zero deallocates and resets to the dangling base; shrinking
+    /// a ZST is a no-op.
+    pub fn shrink(&mut self, new_cap: usize) -> Result<(), GrowError> {
+        // ZSTs are never allocated for, so there is nothing to release.
+        if Self::IS_ZST {
+            return Ok(());
+        }
+
+        debug_assert!(new_cap <= self.capacity, "shrink must never grow");
+        if new_cap == self.capacity {
+            return Ok(());
+        }
+
+        if new_cap == 0 {
+            if self.capacity != 0 {
+                if let Ok(old_layout) = self.layout() {
+                    // SAFETY: `self.elements` was allocated by `self.allocator` with `old_layout`.
+                    unsafe {
+                        self.allocator.deallocate(self.elements.cast::<u8>(), old_layout);
+                    }
+                }
+            }
+            self.elements = NonNull::dangling();
+            self.capacity = 0;
+            return Ok(());
+        }
+
+        let old_layout = self.layout()?;
+        let old_pointer = self.elements.cast::<u8>();
+        // `new_cap < self.capacity`, so the smaller layout is valid and within [isize::MAX].
+        let new_layout = Layout::array::<T>(new_cap)?;
+
+        // SAFETY:
+        // `old_pointer` was allocated by `self.allocator` with `old_layout`, and
+        // `new_layout.size() <= old_layout.size()` because `new_cap < self.capacity`.
+        let allocation = unsafe { self.allocator.shrink(old_pointer, old_layout, new_layout) };
+
+        self.elements = allocation
+            .map_err(|_| GrowError::AllocationFail(new_layout))?
+            .cast::<T>();
+        self.capacity = new_cap;
+
+        Ok(())
+    }
+}
+
+/// Non-generic growth core shared by every `RawDynamicSizeArray<T, A>`.
+///
+/// Keeping the `Layout`/doubling/[MAX_ALLOCATION_SIZE] logic here — reached
+/// through `&dyn Allocator` — means it is compiled once rather than once per
+/// element type, so the VM binary does not bloat as new specializations appear.
+fn grow_raw(
+    allocator: &dyn Allocator,
+    elements: &mut NonNull<u8>,
+    capacity: &mut usize,
+    element_size: usize,
+    align: usize,
+) -> Result<(), GrowError> {
+    // [3]
+    let (new_layout, allocation) = if *capacity == 0 {
+        *capacity = 1;
+        let new_layout = array_layout(element_size, align, *capacity)?;
+        // SAFETY: new capacity == 1. see [1]
+        (new_layout, allocator.allocate(new_layout)) // [1]
+    } else {
+        let old_layout = array_layout(element_size, align, *capacity)?;
+        let old_pointer = *elements;
+
+        *capacity *= 2; // [4]
+        let new_layout = array_layout(element_size, align, *capacity)?; // [5]
+
+        // SAFETY:
+        // `old_pointer` was allocated by `allocator` with `old_layout`. see [1], [2]
+        // `new_layout.size()` is unsigned and not 0. see [3], [4]
+        // `new_layout.size()` <= [isize::MAX]. see [5]
+        (new_layout, unsafe {
+            allocator.grow(old_pointer, old_layout, new_layout) // [2]
+        })
+    };
+
+    *elements = allocation
+        .map_err(|_| GrowError::AllocationFail(new_layout))?
+        .cast::<u8>();
+
+    Ok(())
+}
+
+/// Reconstruct `Layout::array::<T>(capacity)` from raw size/align and enforce
+/// the [MAX_ALLOCATION_SIZE] ceiling, without mentioning `T`.
+fn array_layout(element_size: usize, align: usize, capacity: usize) -> Result<Layout, GrowError> {
+    let size = element_size
+        .checked_mul(capacity)
+        .ok_or(GrowError::AllocationTooLarge)?;
+    let layout = Layout::from_size_align(size, align).map_err(GrowError::Layout)?;
+
+    if layout.size() <= MAX_ALLOCATION_SIZE {
+        Ok(layout)
+    } else {
+        Err(GrowError::AllocationTooLarge)
+    }
 }
 
-impl<T> Drop for RawDynamicSizeArray<T> {
+impl<T, A: Allocator> Drop for RawDynamicSizeArray<T, A> {
     fn drop(&mut self) {
         if self.capacity != 0 && Self::ELEMENT_SIZE != 0 {
             if let Ok(layout) = self.layout() {
                 unsafe {
                     // SAFETY:
-                    // `self.elements` was allocated by the global allocator so can be deallocated by the global allocator.
-                    // `layout` is the same use to deallocate because is exactly the same [Layout] that was used for that allocation, because
+                    // `self.elements` was allocated by `self.allocator` so can be deallocated by it.
+                    // `layout` is exactly the same [Layout] that was used for the allocation, because
                     //  we always compute it with `Layout::array::<T>(self.capacity)`.
-                    dealloc(self.elements.as_ptr() as _, layout);
+                    self.allocator.deallocate(self.elements.cast::<u8>(), layout);
                 }
             }
         }
     }
 }
 
-pub struct DynamicSizeArray<T> {
-    buffer: RawDynamicSizeArray<T>,
+pub struct DynamicSizeArray<T, A: Allocator = Global> {
+    buffer: RawDynamicSizeArray<T, A>,
     length: usize,
 }
 
 // constructors
-impl<T> DynamicSizeArray<T> {
+impl<T> DynamicSizeArray<T, Global> {
     pub const fn new() -> Self {
         Self {
             buffer: RawDynamicSizeArray::new(),
@@ -123,8 +261,97 @@ impl<T> DynamicSizeArray<T> {
     }
 }
 
+impl<T, A: Allocator> DynamicSizeArray<T, A> {
+    /// Create an empty array that routes every allocation through `allocator`.
+    pub const fn new_in(allocator: A) -> Self {
+        Self {
+            buffer: RawDynamicSizeArray::new_in(allocator),
+            length: 0,
+        }
+    }
+}
+
+/// Types whose all-zero bit pattern is a valid, initialized value.
+///
+/// Implemented for the integer primitives so [DynamicSizeArray::with_capacity_zeroed]
+/// can hand back a pre-sized buffer of zeros without an unsafe call site.
+///
+/// # Safety
+/// Implementors promise that transmuting `size_of::<Self>()` zero bytes into
+/// `Self` yields a valid value.
+pub unsafe trait Zeroable {}
+macro_rules! impl_zeroable {
+    ($($element:ty),* $(,)?) => {
+        $(unsafe impl Zeroable for $element {})*
+    };
+}
+impl_zeroable!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl<T> DynamicSizeArray<T, Global> {
+    /// Allocate `cap` elements of zeroed memory and expose them as initialized.
+    ///
+    /// Mirrors the `alloc`/`alloc_zeroed` split in the allocator API: the whole
+    /// buffer is filled in one `alloc_zeroed` instead of a push loop, and
+    /// `length` is set to `cap` so every slot is live.
+    ///
+    /// # Safety
+    /// All `cap` elements are treated as initialized, so an all-zero bit pattern
+    /// must be a valid value of `T`.
+    pub unsafe fn from_zeroed(cap: usize) -> Result<Self, GrowError> {
+        if cap == 0 {
+            return Ok(Self::new());
+        }
+
+        // ZSTs never touch the heap; keep the dangling base and the ZST capacity.
+        if RawDynamicSizeArray::<T, Global>::IS_ZST {
+            return Ok(Self {
+                buffer: RawDynamicSizeArray {
+                    elements: NonNull::dangling(),
+                    capacity: usize::MAX,
+                    allocator: Global,
+                },
+                length: cap,
+            });
+        }
+
+        let layout = Layout::array::<T>(cap)
+            .map_err(GrowError::Layout)
+            .and_then(|layout| {
+                if layout.size() <= MAX_ALLOCATION_SIZE {
+                    Ok(layout)
+                } else {
+                    Err(GrowError::AllocationTooLarge)
+                }
+            })?;
+
+        // SAFETY: `cap != 0` and `T` is not a ZST, so `layout.size()` is non-zero.
+        let pointer = unsafe { alloc_zeroed(layout) };
+        let elements = NonNull::new(pointer as *mut T).ok_or(GrowError::AllocationFail(layout))?;
+
+        Ok(Self {
+            buffer: RawDynamicSizeArray {
+                elements,
+                capacity: cap,
+                allocator: Global,
+            },
+            length: cap,
+        })
+    }
+
+    /// Create an array of `cap` zeroed elements for integer element types.
+    ///
+    /// The [Zeroable] bound makes this the safe counterpart to [Self::from_zeroed].
+    pub fn with_capacity_zeroed(cap: usize) -> Result<Self, GrowError>
+    where
+        T: Zeroable,
+    {
+        // SAFETY: `T: Zeroable` guarantees an all-zero bit pattern is valid.
+        unsafe { Self::from_zeroed(cap) }
+    }
+}
+
 // accessors
-impl<T> DynamicSizeArray<T> {
+impl<T, A: Allocator> DynamicSizeArray<T, A> {
     pub const fn is_empty(&self) -> bool {
         self.length == 0
     }
@@ -143,11 +370,55 @@ impl<T> DynamicSizeArray<T> {
 }
 
 // mutators
-impl<T> DynamicSizeArray<T> {
+impl<T, A: Allocator> DynamicSizeArray<T, A> {
     fn grow(&mut self) -> Result<(), GrowError> {
         self.buffer.grow()
     }
 
+    /// Ensure room for at least `additional` more elements, growing amortized.
+    ///
+    /// If the buffer must grow it jumps to `max(required, capacity * 2)` (with
+    /// the `< 8 => 8` floor) in one reallocation rather than doubling one
+    /// element at a time.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), GrowError> {
+        let required = self.length + additional;
+        if required > self.capacity() {
+            let doubled = self.capacity().saturating_mul(2);
+            let floored = if doubled < 8 { 8 } else { doubled };
+            self.buffer.grow_to(required.max(floored))?;
+        }
+        Ok(())
+    }
+
+    /// Ensure room for at least `additional` more elements, panicking on failure.
+    pub fn reserve(&mut self, additional: usize) {
+        self.try_reserve(additional).unwrap()
+    }
+
+    /// Ensure room for exactly `additional` more elements with no slack.
+    pub fn reserve_exact(&mut self, additional: usize) -> Result<(), GrowError> {
+        let required = self.length + additional;
+        if required > self.capacity() {
+            self.buffer.grow_to(required)?;
+        }
+        Ok(())
+    }
+
+    /// Release any spare capacity so `capacity() == length()`.
+    ///
+    /// For a zero-sized `T` nothing is allocated and the capacity stays `usize::MAX`.
+    pub fn shrink_to_fit(&mut self) {
+        self.buffer.shrink(self.length).unwrap()
+    }
+
+    /// Shrink capacity down towards `min_capacity`, never below the live length.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let target = min_capacity.max(self.length);
+        if target < self.capacity() {
+            self.buffer.shrink(target).unwrap()
+        }
+    }
+
     pub fn push_checked(&mut self, element: T) -> Result<(), GrowError> {
         if self.is_full() {
             self.grow()?;
@@ -257,14 +528,35 @@ impl<T> DynamicSizeArray<T> {
     }
 }
 
-impl<T> Deref for DynamicSizeArray<T> {
+// bulk mutators
+impl<T: Copy, A: Allocator> DynamicSizeArray<T, A> {
+    /// Append every element of `slice`, reserving space for all of them up front.
+    pub fn extend_from_slice(&mut self, slice: &[T]) -> Result<(), GrowError> {
+        self.try_reserve(slice.len())?;
+
+        // SAFETY:
+        // we just reserved `slice.len()` elements so the destination range is in
+        // bounds and properly aligned, `T: Copy` so a bitwise copy is a valid
+        // move, and the two regions belong to different allocations.
+        unsafe {
+            let destination = self.buffer.elements.as_ptr().add(self.length);
+            ptr::copy_nonoverlapping(slice.as_ptr(), destination, slice.len());
+        }
+
+        self.length += slice.len();
+
+        Ok(())
+    }
+}
+
+impl<T, A: Allocator> Deref for DynamicSizeArray<T, A> {
     type Target = [T];
     fn deref(&self) -> &Self::Target {
         unsafe {
             // SAFETY:
             // - `self.raw.elements` is [NonNull] and was created with [Layout::array] so is valid for reads for len * size_of::<T>() bytes,
             //   is properly aligned and The entire memory range of this slice must be contained within a single allocation.
-            // - TODO: data must be non-null and aligned even for zero-length slices or slices of ZSTs.
+            // - `self.buffer.elements` is always non-null and aligned: a ZST or empty buffer keeps the [NonNull::dangling] base, which is valid for zero-length and ZST slices.
             // - Each call to push/insert ensures that each element is a properly initialized value of type T.
             // - returns an shared reference that can't be mutated.
             // - Every call to [Self::grow] ensures the total size of the slice must be no larger than isize::MAX.
@@ -273,13 +565,13 @@ impl<T> Deref for DynamicSizeArray<T> {
     }
 }
 
-impl<T> DerefMut for DynamicSizeArray<T> {
+impl<T, A: Allocator> DerefMut for DynamicSizeArray<T, A> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         unsafe {
             // SAFETY:
             // - `self.raw.elements` is [NonNull] and was created with [Layout::array] so is valid for reads for len * size_of::<T>() bytes,
             //   is properly aligned and The entire memory range of this slice must be contained within a single allocation.
-            // - TODO: data must be non-null and aligned even for zero-length slices or slices of ZSTs.
+            // - `self.buffer.elements` is always non-null and aligned: a ZST or empty buffer keeps the [NonNull::dangling] base, which is valid for zero-length and ZST slices.
             // - Each call to push/insert ensures that each element is a properly initialized value of type T.
             // - returns a mutable reference the borrow checker makes sure this is the only point of access and we dent give out any raw pointers.
             // - Every call to [Self::grow] ensures the total size of the slice must be no larger than isize::MAX.
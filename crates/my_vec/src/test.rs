@@ -14,3 +14,59 @@ fn push_pop() {
         i += 1;
     }
 }
+
+#[test]
+fn grow_sequence_unchanged() {
+    // The polymorphized growth core must keep the original doubling schedule:
+    // 0 -> 1 -> 2 -> 4 -> 8 -> 16 ...
+    let mut array = DynamicSizeArray::new();
+    let mut capacities = Vec::new();
+
+    for value in 0u32..64 {
+        if array.is_full() {
+            array.push(value);
+            capacities.push(array.capacity());
+        } else {
+            array.push(value);
+        }
+    }
+
+    assert_eq!(capacities, [1, 2, 4, 8, 16, 32, 64]);
+}
+
+#[test]
+fn push_pop_unit() {
+    let mut array = DynamicSizeArray::new();
+
+    for _ in 0..1000 {
+        array.push(());
+    }
+    assert_eq!(array.length(), 1000);
+    assert_eq!(array.capacity(), usize::MAX);
+
+    let mut count = 0;
+    while array.pop().is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 1000);
+    assert!(array.is_empty());
+}
+
+#[test]
+fn push_pop_empty_struct() {
+    struct Empty;
+
+    let mut array = DynamicSizeArray::new();
+
+    for _ in 0..1000 {
+        array.push(Empty);
+    }
+    assert_eq!(array.length(), 1000);
+
+    let mut count = 0;
+    while let Some(Empty) = array.pop() {
+        count += 1;
+    }
+    assert_eq!(count, 1000);
+    assert!(array.is_empty());
+}